@@ -14,6 +14,18 @@
 
 //! State presets
 
+/// [Primitive](../../state/struct.Primitive.html) assembly presets.
+pub mod primitive {
+    use state::{Primitive, PrimitiveTopology};
+
+    /// Assemble the vertex stream as an independent triangle per three
+    /// vertices, with no primitive restart.
+    pub const TRIANGLE_LIST: Primitive = Primitive {
+        topology: PrimitiveTopology::TriangleList,
+        restart_index: None,
+    };
+}
+
 /// Blending preset modes.
 pub mod blend {
 	use state::{Blend, BlendChannel, BlendValue, Equation, Factor};
@@ -96,6 +108,21 @@ pub mod blend {
     };
 }
 
+/// [LogicOp](../../state/enum.LogicOp.html) presets.
+pub mod logic {
+    use state::LogicOp;
+
+    /// XOR the fragment with the framebuffer value, the classic trick for
+    /// drawing an invertible (e.g. XOR-draw) cursor.
+    pub const INVERT: LogicOp = LogicOp::Xor;
+
+    /// Replace the framebuffer value with the fragment value.
+    pub const COPY: LogicOp = LogicOp::Copy;
+
+    /// Set the framebuffer value to all zeroes, ignoring the fragment.
+    pub const CLEAR: LogicOp = LogicOp::Clear;
+}
+
 /// [Depth](../../state/struct.Depth.html) presets for depth tests.
 ///
 /// Depth testing is used to avoid drawing "further away" fragments on top of already drawn
@@ -16,11 +16,127 @@
 //!
 //! Configures the primitive assembly (PA), rasterizer, and output merger (OM) blocks.
 
+use std::cmp::Ordering;
 use std::default::Default;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
 use target;
 
+/// A floating-point value that implements `Eq`, `Hash` and `Ord` by
+/// comparing the raw IEEE-754 bit pattern, canonicalizing all NaNs to a
+/// single representative value and `-0.0` to `0.0`.
+///
+/// Hardware state blocks such as [`Offset`](struct.Offset.html) need to
+/// satisfy `Hash`/`Eq` so they can be cached and diffed by value, which
+/// `f32` alone does not support.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+pub struct OrderedF32(pub f32);
+
+impl OrderedF32 {
+    fn key(&self) -> u32 {
+        if self.0.is_nan() {
+            f32::NAN.to_bits()
+        } else if self.0 == 0.0 {
+            0.0f32.to_bits()
+        } else {
+            self.0.to_bits()
+        }
+    }
+}
+
+impl PartialEq for OrderedF32 {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for OrderedF32 {}
+
+impl PartialOrd for OrderedF32 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedF32 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl Hash for OrderedF32 {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
+}
+
+/// A value that can either be baked into a pipeline, or left to be supplied
+/// separately at draw time.
+///
+/// Backends that build immutable pipeline objects (Vulkan/Metal-style) need
+/// to know which values are truly static -- and thus force a pipeline
+/// rebuild when they change -- versus which are `Dynamic` and can be set at
+/// record time without touching the pipeline. `Static(v)` means `v` is
+/// compiled in; `Dynamic` means the value will be supplied separately per
+/// draw, so a consumer diffing two pipeline states can ignore dynamic
+/// fields when deciding whether a rebuild is required.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+pub enum State<T> {
+    /// The value is fixed and baked into the pipeline.
+    Static(T),
+    /// The value is supplied separately at draw time.
+    Dynamic,
+}
+
+impl<T> State<T> {
+    /// Extract the static value, falling back to `default` if dynamic.
+    pub fn static_or(self, default: T) -> T {
+        match self {
+            State::Static(v) => v,
+            State::Dynamic => default,
+        }
+    }
+}
+
+impl<T: Default> Default for State<T> {
+    fn default() -> Self {
+        State::Static(T::default())
+    }
+}
+
+/// How the input vertex stream is assembled into primitives.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+pub enum PrimitiveTopology {
+    PointList,
+    LineList,
+    LineStrip,
+    TriangleList,
+    TriangleStrip,
+    LineListAdjacency,
+    LineStripAdjacency,
+    TriangleListAdjacency,
+    TriangleStripAdjacency,
+    /// Patch list with the given number of control points, for tessellation.
+    PatchList(u8),
+}
+
+/// Primitive assembly (PA) state: how the vertex stream is grouped into
+/// primitives before rasterization.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+pub struct Primitive {
+    /// The primitive topology to assemble.
+    pub topology: PrimitiveTopology,
+    /// Index value that restarts a strip topology, if primitive restart is
+    /// enabled.
+    pub restart_index: Option<u32>,
+}
+
 /// The front face winding order of a set of vertices.
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 #[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
@@ -34,18 +150,49 @@ pub enum FrontFace {
 /// Width of a line.
 /// Could be f32 if not for Hash deriving issues.
 pub type LineWidth = i32;
-/// Slope depth offset factor
-/// Could be f32 if not for Hash deriving issues.
-pub type OffsetSlope = i32;
 /// Number of units to offset, where
 /// the unit is the minimal difference in the depth value
 /// dictated by the precision of the depth buffer.
 pub type OffsetUnits = i32;
 
 /// How to offset vertices in screen space, if at all.
+///
+/// Corresponds to the depth-bias knobs exposed by modern pipeline
+/// descriptors: a slope-scaled term, a constant term, and a clamp on the
+/// total bias magnitude (which prevents z-fighting artifacts from blowing
+/// up on steep polygons).
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd)]
 #[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
-pub struct Offset(pub OffsetSlope, pub OffsetUnits);
+pub struct Offset {
+    /// Factor applied to the polygon's maximum depth slope.
+    pub slope_factor: OrderedF32,
+    /// Constant depth offset, in units of the minimal resolvable difference
+    /// in the depth buffer.
+    pub constant_units: OffsetUnits,
+    /// Maximum (or, if negative, minimum) magnitude of the resulting bias.
+    pub clamp: OrderedF32,
+}
+
+impl Offset {
+    /// Construct an offset from the legacy integer slope/units pair, with
+    /// no clamp. Provided for compatibility with code written against the
+    /// old fixed-point representation.
+    pub fn new(slope_factor: i32, constant_units: OffsetUnits) -> Self {
+        Offset {
+            slope_factor: OrderedF32(slope_factor as f32),
+            constant_units: constant_units,
+            clamp: OrderedF32(0.0),
+        }
+    }
+
+    /// Set the depth-bias clamp.
+    pub fn with_clamp(self, clamp: f32) -> Self {
+        Offset {
+            clamp: OrderedF32(clamp),
+            ..self
+        }
+    }
+}
 
 /// Which face, if any, to cull.
 #[allow(missing_docs)]
@@ -64,7 +211,7 @@ pub enum RasterMethod {
     /// Rasterize as a point.
     Point,
     /// Rasterize as a line with the given width.
-    Line(LineWidth),
+    Line(State<LineWidth>),
     /// Rasterize as a face.
     Fill
 }
@@ -88,9 +235,17 @@ pub struct Rasterizer {
     /// How to rasterize this primitive.
     pub method: RasterMethod,
     /// Any polygon offset to apply.
-    pub offset: Option<Offset>,
+    pub offset: Option<State<Offset>>,
     /// Multi-sampling mode.
     pub samples: Option<MultiSample>,
+    /// When `true`, fragments outside the near/far planes are clipped
+    /// (discarded) as usual. When `false`, they are clamped to the near/far
+    /// planes instead of being discarded -- the classic "depth clamp" used
+    /// for shadow-volume and skybox rendering.
+    pub depth_clip: bool,
+    /// Selects the `[0, 1]` clip-space depth convention (D3D/Vulkan/Metal)
+    /// when `true`, versus the legacy `[-1, 1]` convention (GL) when `false`.
+    pub clip_half_z: bool,
 }
 
 impl Rasterizer {
@@ -102,6 +257,8 @@ impl Rasterizer {
             method: RasterMethod::Fill,
             offset: None,
             samples: None,
+            depth_clip: true,
+            clip_half_z: false,
         }
     }
 
@@ -113,10 +270,31 @@ impl Rasterizer {
         }
     }
 
+    /// Clamp fragments outside the near/far planes instead of discarding them.
+    pub fn with_depth_clamp(self) -> Self {
+        Rasterizer {
+            depth_clip: false,
+            ..self
+        }
+    }
+
+    /// Use the `[0, 1]` clip-space depth convention (D3D/Vulkan/Metal)
+    /// instead of the legacy `[-1, 1]` convention (GL).
+    pub fn with_half_z(self) -> Self {
+        Rasterizer {
+            clip_half_z: true,
+            ..self
+        }
+    }
+
     /// Add polygon offset.
-    pub fn with_offset(self, slope: f32, units: OffsetUnits) -> Self {
+    pub fn with_offset(self, slope_factor: f32, units: OffsetUnits) -> Self {
         Rasterizer {
-            offset: Some(Offset(slope as OffsetSlope, units)),
+            offset: Some(State::Static(Offset {
+                slope_factor: OrderedF32(slope_factor),
+                constant_units: units,
+                clamp: OrderedF32(0.0),
+            })),
             ..self
         }
     }
@@ -175,9 +353,9 @@ pub struct StencilSide {
     pub fun: Comparison,
     /// A mask that is ANDd with both the stencil buffer value and the reference value when they
     /// are read before doing the stencil test.
-    pub mask_read: target::Stencil,
+    pub mask_read: State<target::Stencil>,
     /// A mask that is ANDd with the stencil value before writing to the stencil buffer.
-    pub mask_write: target::Stencil,
+    pub mask_write: State<target::Stencil>,
     /// What operation to do if the stencil test fails.
     pub op_fail: StencilOp,
     /// What operation to do if the stenil test passes but the depth test fails.
@@ -190,8 +368,8 @@ impl Default for StencilSide {
     fn default() -> Self {
         StencilSide {
             fun: Comparison::Always,
-            mask_read: target::Stencil::max_value(),
-            mask_write: target::Stencil::max_value(),
+            mask_read: State::Static(target::Stencil::max_value()),
+            mask_write: State::Static(target::Stencil::max_value()),
             op_fail: StencilOp::Keep,
             op_depth_fail: StencilOp::Keep,
             op_pass: StencilOp::Keep,
@@ -215,8 +393,8 @@ impl Stencil {
                -> Self {
         let side = StencilSide {
             fun: fun,
-            mask_read: mask,
-            mask_write: mask,
+            mask_read: State::Static(mask),
+            mask_write: State::Static(mask),
             op_fail: ops.0,
             op_depth_fail: ops.1,
             op_pass: ops.2,
@@ -374,22 +552,107 @@ impl Default for Color {
     }
 }
 
+/// Bitwise logic operation, applied between a fragment and the framebuffer
+/// in place of blending.
+///
+/// Logic ops and [`Blend`](struct.Blend.html) are mutually exclusive ways
+/// for fixed-function hardware to combine a fragment with the framebuffer:
+/// when a logic op is enabled, any `Blend` equation on the targets is
+/// ignored. Logic ops only apply to integer/normalized color targets.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+pub enum LogicOp {
+    Clear,
+    And,
+    AndReverse,
+    Copy,
+    AndInverted,
+    NoOp,
+    Xor,
+    Or,
+    Nor,
+    Equiv,
+    Invert,
+    OrReverse,
+    CopyInverted,
+    OrInverted,
+    Nand,
+    Set,
+}
+
+/// A color target bound at a particular attachment index, as part of a
+/// multiple-render-target (MRT) output merger.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+pub struct ColorTarget {
+    /// Index of the attachment this target is bound to.
+    pub index: u8,
+    /// Mask/blend state for this attachment.
+    pub color: Color,
+}
+
+/// Output-merger state for one or more independent color render targets.
+///
+/// Modern APIs can bind several color attachments at once, each with its own
+/// mask and blend equation (e.g. an MRT pass writing albedo with no blend
+/// and an accumulation target with additive blend).
+#[derive(Clone, Debug, Eq, Hash, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+pub struct Blender {
+    /// Per-target color state, in attachment binding order.
+    ///
+    /// `with_target` enforces that indices are unique; constructing this
+    /// field directly bypasses that check.
+    pub targets: Vec<ColorTarget>,
+    /// Global logic operation. When set, it replaces blending on every
+    /// target (see [`LogicOp`](enum.LogicOp.html)).
+    pub logic: Option<LogicOp>,
+}
+
+impl Blender {
+    /// Create a blender with a single color target at attachment index 0,
+    /// matching the behavior of a single-target `Color` descriptor.
+    pub fn single(color: Color) -> Self {
+        Blender {
+            targets: vec![ColorTarget { index: 0, color: color }],
+            logic: None,
+        }
+    }
+
+    /// Add another color target at `index`.
+    ///
+    /// Panics if `index` is already bound by another target.
+    pub fn with_target(mut self, index: u8, color: Color) -> Self {
+        assert!(self.targets.iter().all(|t| t.index != index),
+                "color target index {} is already bound", index);
+        self.targets.push(ColorTarget { index: index, color: color });
+        self
+    }
+}
+
+impl Default for Blender {
+    fn default() -> Self {
+        Blender::single(Color::default())
+    }
+}
+
 /// The complete set of the rasterizer reference values.
 /// Switching these doesn't roll the hardware context.
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
 #[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
 pub struct RefValues {
     /// Stencil front and back values.
-    pub stencil: (target::Stencil, target::Stencil),
+    pub stencil: State<(target::Stencil, target::Stencil)>,
     /// Constant blend color.
-    pub blend: target::ColorValue,
+    pub blend: State<target::ColorValue>,
 }
 
 impl Default for RefValues {
     fn default() -> Self {
         RefValues {
-            stencil: (0, 0),
-            blend: [0f32; 4],
+            stencil: State::Static((0, 0)),
+            blend: State::Static([0f32; 4]),
         }
     }
 }